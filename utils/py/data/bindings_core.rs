@@ -0,0 +1,55 @@
+//! Transport-agnostic entry point for embedding mm2 in foreign runtimes (Node.js via neon,
+//! Python via pyo3, WASM via wasm-bindgen) without standing up a local HTTP/TCP listener.
+//!
+//! Each language binding is a thin crate that depends on this one and only has to marshal
+//! `ctx_handle`/`request_json`/the returned JSON string across its own FFI boundary; the
+//! dispatcher match arms in `dispatcher.rs` remain the single source of truth for the method
+//! surface, so adding an RPC there is enough for it to show up in every embedding.
+
+use crate::rpc::dispatcher::dispatcher::process_single_request;
+use mm2_core::mm_ctx::{MmArc, MmCtxBuilder};
+use serde_json::{self as json, Value as Json};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// Loopback address stamped on every embedded call so the dispatcher's `local_only` check,
+/// rate limiter, and auth flow behave exactly as they would for a real localhost HTTP client.
+const LOOPBACK_CLIENT: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+/// Opaque handle an embedder holds on to between `init_ctx` and `call_rpc` calls; wraps the same
+/// `MmArc` the HTTP server would use, so behavior is identical whether a binding or a socket
+/// is driving the dispatcher.
+///
+/// The `MmArc` itself is passed across the FFI boundary (each binding marshals this struct, not a
+/// bare integer/pointer), so there's no separate handle table to look anything up in.
+#[derive(Clone)]
+pub struct CtxHandle(MmArc);
+
+impl CtxHandle {
+    pub fn new(ctx: MmArc) -> Self { CtxHandle(ctx) }
+}
+
+/// Builds an `MmArc` from the same `conf` JSON the native binary takes on startup, for embedders
+/// that don't want to go through `MmCtxBuilder` directly.
+pub fn init_ctx(conf: Json) -> Result<CtxHandle, String> {
+    let ctx = MmCtxBuilder::new().with_conf(conf).into_mm_arc();
+    Ok(CtxHandle::new(ctx))
+}
+
+/// Single entry point for foreign-runtime bindings: takes the raw request JSON, funnels it
+/// through `process_single_request` as if it arrived over loopback HTTP, and returns the
+/// serialized `Response` body as a string so no binding has to link against `http::Response`.
+pub async fn call_rpc(ctx_handle: CtxHandle, request_json: String) -> String {
+    let req: Json = match json::from_str(&request_json) {
+        Ok(req) => req,
+        Err(e) => return error_response_json(&format!("Invalid request JSON: {}", e)),
+    };
+
+    match process_single_request(ctx_handle.0, req, LOOPBACK_CLIENT, true).await {
+        Ok(response) => String::from_utf8_lossy(response.body()).into_owned(),
+        Err(e) => error_response_json(&e.to_string()),
+    }
+}
+
+fn error_response_json(message: &str) -> String {
+    json::json!({ "error": message }).to_string()
+}
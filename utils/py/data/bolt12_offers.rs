@@ -0,0 +1,160 @@
+//! BOLT12 offers for lightning coins, layered on top of the existing BOLT11
+//! `payments::generate_invoice`/`payments::send_payment` pair.
+//!
+//! A BOLT12 offer is a static, reusable payment code (bech32-encoded with the `lno` human
+//! readable prefix) that carries no amount-locked invoice and no single-payment expiry, so one
+//! offer can be paid any number of times. The handshake is:
+//! 1. The receiver publishes an offer, optionally with a fixed amount, description, and a
+//!    blinded path that hides the receiving node id.
+//! 2. A payer decodes the offer and sends an `invoice_request` over onion messages.
+//! 3. The receiver answers with a signed `Bolt12Invoice`.
+//! 4. The payer pays that invoice over a normal lightning route, same as a BOLT11 payment.
+//!
+//! A refund is the same handshake in reverse: the payer publishes a refund offer and the
+//! original receiver pays it back.
+
+use coins::lightning::LightningCoin;
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use lightning::offers::invoice::Bolt12Invoice;
+use lightning::offers::offer::Offer;
+use lightning::offers::refund::Refund;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub type Bolt12Result<T> = Result<T, MmError<Bolt12Error>>;
+
+#[derive(Serialize, Display, Debug)]
+pub enum Bolt12Error {
+    #[display(fmt = "No such coin '{}' or it's not a lightning coin", _0)]
+    NoSuchCoin(String),
+    #[display(fmt = "Invalid BOLT12 offer/refund string: {}", _0)]
+    InvalidOfferString(String),
+    #[display(fmt = "Offer requires an amount to be specified, it has none")]
+    AmountRequired,
+    #[display(fmt = "Timed out waiting for the invoice_request/invoice onion-message round trip")]
+    OnionMessageTimeout,
+    #[display(fmt = "Failed to pay the decoded BOLT12 invoice: {}", _0)]
+    PaymentError(String),
+}
+
+impl HttpStatusCode for Bolt12Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Bolt12Error::NoSuchCoin(_) => StatusCode::NOT_FOUND,
+            Bolt12Error::InvalidOfferString(_) | Bolt12Error::AmountRequired => StatusCode::BAD_REQUEST,
+            Bolt12Error::OnionMessageTimeout | Bolt12Error::PaymentError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOfferRequest {
+    pub coin: String,
+    /// If omitted, the offer is amount-less and the payer chooses how much to send.
+    pub amount_in_msat: Option<u64>,
+    pub description: String,
+    /// Hides the node id behind a blinded path instead of advertising it in the offer directly.
+    #[serde(default)]
+    pub use_blinded_path: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateOfferResponse {
+    /// The `lno...`-encoded offer, to be shared out of band (QR code, link, etc).
+    pub offer: String,
+}
+
+/// Publishes a reusable offer and persists it so incoming `invoice_request`s are answered
+/// automatically for as long as the node is online.
+pub async fn create_offer(ctx: MmArc, req: CreateOfferRequest) -> Bolt12Result<CreateOfferResponse> {
+    let coin = lightning_coin_from_ctx(&ctx, &req.coin)?;
+    let mut builder = coin.create_offer_builder(req.description).mm_err(|e| Bolt12Error::InvalidOfferString(e.to_string()))?;
+    if let Some(amount_msat) = req.amount_in_msat {
+        builder = builder.amount_msats(amount_msat);
+    }
+    if req.use_blinded_path {
+        builder = builder.path(coin.blinded_path().await);
+    }
+
+    let offer: Offer = builder.build().mm_err(|e| Bolt12Error::InvalidOfferString(e.to_string()))?;
+    coin.persist_offer(&offer).await;
+    Ok(CreateOfferResponse { offer: offer.to_string() })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayOfferRequest {
+    pub coin: String,
+    pub offer: String,
+    /// Required if the offer itself is amount-less.
+    pub amount_in_msat: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayOfferResponse {
+    pub payment_hash: String,
+    pub amount_in_msat: u64,
+}
+
+/// Decodes `offer`, sends an `invoice_request` over onion messages, waits for the signed
+/// `Bolt12Invoice` reply, then pays it over a normal route exactly like a BOLT11 payment.
+pub async fn pay_offer(ctx: MmArc, req: PayOfferRequest) -> Bolt12Result<PayOfferResponse> {
+    let coin = lightning_coin_from_ctx(&ctx, &req.coin)?;
+    let offer: Offer = req.offer.parse().map_to_mm(|_| Bolt12Error::InvalidOfferString(req.offer.clone()))?;
+
+    let amount_msat = match (offer.amount(), req.amount_in_msat) {
+        (Some(amount), _) => amount.msats(),
+        (None, Some(amount)) => amount,
+        (None, None) => return MmError::err(Bolt12Error::AmountRequired),
+    };
+
+    let invoice: Bolt12Invoice = coin
+        .request_invoice(&offer, amount_msat)
+        .await
+        .mm_err(|_| Bolt12Error::OnionMessageTimeout)?;
+
+    let payment_hash = coin
+        .pay_bolt12_invoice(&invoice)
+        .await
+        .mm_err(|e| Bolt12Error::PaymentError(e.to_string()))?;
+
+    Ok(PayOfferResponse {
+        payment_hash,
+        amount_in_msat: amount_msat,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequestRefundRequest {
+    pub coin: String,
+    pub amount_in_msat: u64,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequestRefundResponse {
+    /// The payer-published refund; the original receiver decodes and pays it back.
+    pub refund: String,
+}
+
+/// The inverse of `pay_offer`: publishes a refund that a previous payment's receiver can pay back.
+pub async fn request_refund(ctx: MmArc, req: RequestRefundRequest) -> Bolt12Result<RequestRefundResponse> {
+    let coin = lightning_coin_from_ctx(&ctx, &req.coin)?;
+    let refund: Refund = coin
+        .create_refund_builder(req.description, req.amount_in_msat)
+        .mm_err(|e| Bolt12Error::InvalidOfferString(e.to_string()))?
+        .build()
+        .mm_err(|e| Bolt12Error::InvalidOfferString(e.to_string()))?;
+    coin.persist_refund(&refund).await;
+    Ok(RequestRefundResponse { refund: refund.to_string() })
+}
+
+fn lightning_coin_from_ctx(ctx: &MmArc, ticker: &str) -> Bolt12Result<LightningCoin> {
+    coins::lp_coinfind(ctx, ticker)
+        .ok()
+        .flatten()
+        .and_then(|coin| coin.as_lightning_coin())
+        .or_mm_err(|| Bolt12Error::NoSuchCoin(ticker.to_owned()))
+}
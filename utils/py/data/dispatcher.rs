@@ -12,19 +12,27 @@ use crate::lp_swap::swap_v2_rpcs::{active_swaps_rpc, my_recent_swaps_rpc, my_swa
 use crate::lp_swap::{get_locked_amount_rpc, max_maker_vol, recreate_swap_data, trade_preimage_rpc};
 use crate::lp_wallet::{change_mnemonic_password, get_mnemonic_rpc, get_wallet_names_rpc};
 use crate::rpc::lp_commands::db_id::get_shared_db_id;
+use crate::rpc::lp_commands::lightning_bitcoind_backend::attach_bitcoind_backend;
 use crate::rpc::lp_commands::one_inch::rpcs::{one_inch_v6_0_classic_swap_contract_rpc,
                                               one_inch_v6_0_classic_swap_create_rpc,
                                               one_inch_v6_0_classic_swap_liquidity_sources_rpc,
                                               one_inch_v6_0_classic_swap_quote_rpc,
                                               one_inch_v6_0_classic_swap_tokens_rpc};
 use crate::rpc::lp_commands::pubkey::*;
+use crate::rpc::lp_commands::recover_swap_funds::recover_swap_funds_rpc;
+use crate::rpc::lp_commands::stream_multiplexer::{enable_orderbook_multiplexed, enable_tx_history_multiplexed};
+use crate::rpc::lp_commands::streamer_filters::{update_orderbook_filter, update_tx_history_filter};
+use crate::rpc::lp_commands::swap_events_streaming::enable_swap_events;
 use crate::rpc::lp_commands::tokens::get_token_info;
+use crate::rpc::lp_commands::tx_history_target::my_tx_history_rpc;
 use crate::rpc::lp_commands::tokens::{approve_token_rpc, get_token_allowance_rpc};
 use crate::rpc::lp_commands::trezor::trezor_connection_status;
+use crate::rpc::lp_commands::wallet_connect::{delete_connection, get_account, get_sessions, new_connection,
+                                              ping_session, sign_message_wc_aware, sign_raw_transaction_wc_aware,
+                                              withdraw_wc_aware};
 use crate::rpc::rate_limiter::{process_rate_limit, RateLimitContext};
 use coins::eth::fee_estimation::rpc::get_eth_estimated_fee_per_gas;
 use coins::eth::EthCoin;
-use coins::my_tx_history_v2::my_tx_history_v2_rpc;
 use coins::rpc_command::tendermint::{ibc_chains, ibc_transfer_channels};
 use coins::rpc_command::{account_balance::account_balance,
                          get_current_mtp::get_current_mtp_rpc,
@@ -47,8 +55,7 @@ use coins::utxo::utxo_standard::UtxoStandardCoin;
 use coins::z_coin::ZCoin;
 use coins::{add_delegation, claim_staking_rewards, delegations_info, get_my_address, get_raw_transaction,
             get_swap_transaction_fee_policy, nft, ongoing_undelegations_info, remove_delegation,
-            set_swap_transaction_fee_policy, sign_message, sign_raw_transaction, validators_info, verify_message,
-            withdraw};
+            set_swap_transaction_fee_policy, validators_info, verify_message};
 use coins_activation::{cancel_init_l2, cancel_init_platform_coin_with_tokens, cancel_init_standalone_coin,
                        cancel_init_token, enable_platform_coin_with_tokens, enable_token, init_l2, init_l2_status,
                        init_l2_user_action, init_platform_coin_with_tokens, init_platform_coin_with_tokens_status,
@@ -77,6 +84,54 @@ pub async fn process_single_request(
     req: Json,
     client: SocketAddr,
     local_only: bool,
+) -> DispatcherResult<Response<Vec<u8>>> {
+    // A batch is a plain JSON array of individual requests (JSON-RPC 2.0 style); fan it out so a
+    // GUI can e.g. enable several coins or poll several `orderbook`/`my_swap_status` calls in one
+    // round trip instead of paying per-request transport overhead.
+    if let Json::Array(requests) = req {
+        return process_batch_request(ctx, requests, client, local_only).await;
+    }
+
+    dispatch_one_request(ctx, req, client, local_only).await
+}
+
+/// Dispatches every element of a batch independently so that one failing call doesn't abort the
+/// others, preserving each element's own `id` in the corresponding position of the response array.
+async fn process_batch_request(
+    ctx: MmArc,
+    requests: Vec<Json>,
+    client: SocketAddr,
+    local_only: bool,
+) -> DispatcherResult<Response<Vec<u8>>> {
+    let mut bodies = Vec::with_capacity(requests.len());
+    for req in requests {
+        // Grabbed from the raw JSON (rather than the parsed `MmRpcRequest`) because a request that
+        // fails to even parse never produces one; falls back to `null` for a malformed element,
+        // same as `MmRpcBuilder` does for an absent id on the success/handler-error path.
+        let id = req.get("id").cloned().unwrap_or(Json::Null);
+        let body = match dispatch_one_request(ctx.clone(), req, client, local_only).await {
+            Ok(response) => response.into_body(),
+            Err(e) => batch_element_error_body(e, id),
+        };
+        bodies.push(json::from_slice::<Json>(&body).unwrap_or(Json::Null));
+    }
+
+    let body = json::to_vec(&Json::Array(bodies))?;
+    Ok(Response::new(body))
+}
+
+/// Renders a request-level error (auth/rate-limit/local-only, or a malformed element) that happened
+/// before a batch element ever reached `handle_mmrpc`, still carrying `id` so a GUI correlating
+/// batch responses to requests by `id` doesn't silently mis-match on these failure modes.
+fn batch_element_error_body(error: MmError<DispatcherError>, id: Json) -> Vec<u8> {
+    json::to_vec(&json::json!({ "error": error.to_string(), "id": id })).unwrap_or_default()
+}
+
+async fn dispatch_one_request(
+    ctx: MmArc,
+    req: Json,
+    client: SocketAddr,
+    local_only: bool,
 ) -> DispatcherResult<Response<Vec<u8>>> {
     let request: MmRpcRequest = json::from_value(req)?;
 
@@ -180,6 +235,11 @@ async fn dispatcher_v2(request: MmRpcRequest, ctx: MmArc) -> DispatcherResult<Re
         return gui_storage_dispatcher(request, ctx, &gui_storage_method).await;
     }
 
+    if let Some(wc_method) = request.method.strip_prefix("wc::") {
+        let wc_method = wc_method.to_owned();
+        return walletconnect_dispatcher(request, ctx, &wc_method).await;
+    }
+
     if let Some(experimental_method) = request.method.strip_prefix("experimental::") {
         let experimental_method = experimental_method.to_string();
         return experimental_rpcs_dispatcher(request, ctx, &experimental_method).await;
@@ -226,13 +286,17 @@ async fn dispatcher_v2(request: MmRpcRequest, ctx: MmArc) -> DispatcherResult<Re
         "max_maker_vol" => handle_mmrpc(ctx, request, max_maker_vol).await,
         "my_recent_swaps" => handle_mmrpc(ctx, request, my_recent_swaps_rpc).await,
         "my_swap_status" => handle_mmrpc(ctx, request, my_swap_status_rpc).await,
-        "my_tx_history" => handle_mmrpc(ctx, request, my_tx_history_v2_rpc).await,
+        // `target` selects the iguana address, an HD account/address, or an explicit address list.
+        "my_tx_history" => handle_mmrpc(ctx, request, my_tx_history_rpc).await,
         "orderbook" => handle_mmrpc(ctx, request, orderbook_rpc_v2).await,
+        "recover_swap_funds" => handle_mmrpc(ctx, request, recover_swap_funds_rpc).await,
         "recreate_swap_data" => handle_mmrpc(ctx, request, recreate_swap_data).await,
         "refresh_nft_metadata" => handle_mmrpc(ctx, request, refresh_nft_metadata).await,
         "remove_node_from_version_stat" => handle_mmrpc(ctx, request, remove_node_from_version_stat).await,
-        "sign_message" => handle_mmrpc(ctx, request, sign_message).await,
-        "sign_raw_transaction" => handle_mmrpc(ctx, request, sign_raw_transaction).await,
+        // Forward to the connected external wallet when the coin is WalletConnect-backed, else
+        // sign with a local keypair exactly as before.
+        "sign_message" => handle_mmrpc(ctx, request, sign_message_wc_aware).await,
+        "sign_raw_transaction" => handle_mmrpc(ctx, request, sign_raw_transaction_wc_aware).await,
         "start_simple_market_maker_bot" => handle_mmrpc(ctx, request, start_simple_market_maker_bot).await,
         "start_version_stat_collection" => handle_mmrpc(ctx, request, start_version_stat_collection).await,
         "stop_simple_market_maker_bot" => handle_mmrpc(ctx, request, stop_simple_market_maker_bot).await,
@@ -243,7 +307,7 @@ async fn dispatcher_v2(request: MmRpcRequest, ctx: MmArc) -> DispatcherResult<Re
         "change_mnemonic_password" => handle_mmrpc(ctx, request, change_mnemonic_password).await,
         "update_version_stat_collection" => handle_mmrpc(ctx, request, update_version_stat_collection).await,
         "verify_message" => handle_mmrpc(ctx, request, verify_message).await,
-        "withdraw" => handle_mmrpc(ctx, request, withdraw).await,
+        "withdraw" => handle_mmrpc(ctx, request, withdraw_wc_aware).await,
         "ibc_chains" => handle_mmrpc(ctx, request, ibc_chains).await,
         "ibc_transfer_channels" => handle_mmrpc(ctx, request, ibc_transfer_channels).await,
         "peer_connection_healthcheck" => handle_mmrpc(ctx, request, peer_connection_healthcheck_rpc).await,
@@ -373,6 +437,12 @@ async fn rpc_task_dispatcher(
     }
 }
 
+/// `stream` dispatcher.
+///
+/// `orderbook::enable` and `swap_events::enable` push compact deltas onto the ctx event bus as
+/// they happen (orderbook diffs, swap state-machine transitions) instead of requiring the client
+/// to poll `orderbook`/`my_swap_status`; both are disabled the same way as every other streamer,
+/// via `disable`.
 async fn rpc_streaming_dispatcher(
     request: MmRpcRequest,
     ctx: MmArc,
@@ -386,7 +456,18 @@ async fn rpc_streaming_dispatcher(
         "swap_status::enable" => handle_mmrpc(ctx, request, streaming_activations::enable_swap_status).await,
         "order_status::enable" => handle_mmrpc(ctx, request, streaming_activations::enable_order_status).await,
         "tx_history::enable" => handle_mmrpc(ctx, request, streaming_activations::enable_tx_history).await,
+        // Fans a single `tx_history` streamer out across several redundant Electrum endpoints
+        // instead of a single upstream; see `stream_multiplexer` for the fastest-wins/failover logic.
+        "tx_history::enable_multiplexed" => handle_mmrpc(ctx, request, enable_tx_history_multiplexed).await,
+        "tx_history::update_filter" => handle_mmrpc(ctx, request, update_tx_history_filter).await,
         "orderbook::enable" => handle_mmrpc(ctx, request, streaming_activations::enable_orderbook).await,
+        // Same fastest-wins/failover fan-out as `tx_history::enable_multiplexed`, for orderbook diffs.
+        "orderbook::enable_multiplexed" => handle_mmrpc(ctx, request, enable_orderbook_multiplexed).await,
+        "orderbook::update_filter" => handle_mmrpc(ctx, request, update_orderbook_filter).await,
+        // Not a `streaming_activations` branch like most of its siblings above: it's event-driven off the
+        // swap state machine rather than polled on an interval, so it lives in its own module; see
+        // `swap_events_streaming`.
+        "swap_events::enable" => handle_mmrpc(ctx, request, enable_swap_events).await,
         "disable" => handle_mmrpc(ctx, request, streaming_activations::disable_streamer).await,
         _ => MmError::err(DispatcherError::NoSuchMethod),
     }
@@ -420,11 +501,34 @@ async fn gui_storage_dispatcher(
     }
 }
 
+/// `wc` dispatcher for the WalletConnect session + signing subsystem.
+///
+/// # Note
+///
+/// `wc_method` is a method name with the `wc::` prefix removed.
+async fn walletconnect_dispatcher(
+    request: MmRpcRequest,
+    ctx: MmArc,
+    wc_method: &str,
+) -> DispatcherResult<Response<Vec<u8>>> {
+    match wc_method {
+        "new_connection" => handle_mmrpc(ctx, request, new_connection).await,
+        "get_sessions" => handle_mmrpc(ctx, request, get_sessions).await,
+        "ping_session" => handle_mmrpc(ctx, request, ping_session).await,
+        "delete_connection" => handle_mmrpc(ctx, request, delete_connection).await,
+        "get_account" => handle_mmrpc(ctx, request, get_account).await,
+        _ => MmError::err(DispatcherError::NoSuchMethod),
+    }
+}
+
 /// `lightning` dispatcher.
 ///
 /// # Note
 ///
-/// `lightning_method` is a method name with the `lightning::` prefix removed.
+/// `lightning_method` is a method name with the `lightning::` prefix removed. Activation itself
+/// happens through `task::enable_lightning::*` in `rpc_task_dispatcher`; `attach_bitcoind_backend`
+/// below switches an already-activated coin's chain source over to a self-hosted `bitcoind` node
+/// instead of the default Electrum plumbing.
 #[cfg(not(target_arch = "wasm32"))]
 async fn lightning_dispatcher(
     request: MmRpcRequest,
@@ -449,10 +553,21 @@ async fn lightning_dispatcher(
         "nodes::connect_to_node" => handle_mmrpc(ctx, request, nodes::connect_to_node).await,
         "nodes::list_trusted_nodes" => handle_mmrpc(ctx, request, nodes::list_trusted_nodes).await,
         "nodes::remove_trusted_node" => handle_mmrpc(ctx, request, nodes::remove_trusted_node).await,
+        // Fetches a Rapid Gossip Sync snapshot and applies it to the in-memory routing graph,
+        // so mobile/wasm nodes don't have to rebuild it from slow, bandwidth-heavy p2p gossip.
+        "sync_network_graph" => handle_mmrpc(ctx, request, nodes::sync_network_graph).await,
         "payments::generate_invoice" => handle_mmrpc(ctx, request, payments::generate_invoice).await,
         "payments::get_payment_details" => handle_mmrpc(ctx, request, payments::get_payment_details).await,
         "payments::list_payments_by_filter" => handle_mmrpc(ctx, request, payments::list_payments_by_filter).await,
         "payments::send_payment" => handle_mmrpc(ctx, request, payments::send_payment).await,
+        // BOLT12 offers: a reusable, amount-optional `lno...`-encoded payment code, backed by
+        // LDK's offers machinery. Unlike a BOLT11 invoice, one offer can be paid repeatedly.
+        "payments::create_offer" => handle_mmrpc(ctx, request, payments::create_offer).await,
+        "payments::pay_offer" => handle_mmrpc(ctx, request, payments::pay_offer).await,
+        "payments::request_refund" => handle_mmrpc(ctx, request, payments::request_refund).await,
+        // Points an already-activated coin's chain source at a self-hosted bitcoind node instead
+        // of the default Electrum plumbing; see `lightning_bitcoind_backend`.
+        "attach_bitcoind_backend" => handle_mmrpc(ctx, request, attach_bitcoind_backend).await,
         _ => MmError::err(DispatcherError::NoSuchMethod),
     }
 }
@@ -486,4 +601,24 @@ async fn staking_dispatcher(
         "undelegate" => handle_mmrpc(ctx, request, remove_delegation).await,
         _ => MmError::err(DispatcherError::NoSuchMethod),
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_element_error_body_carries_the_request_id() {
+        let body = batch_element_error_body(MmError::new(DispatcherError::NoSuchMethod), Json::from(7));
+        let parsed: Json = json::from_slice(&body).unwrap();
+        assert_eq!(parsed["id"], Json::from(7));
+        assert!(parsed["error"].is_string());
+    }
+
+    #[test]
+    fn batch_element_error_body_carries_a_null_id() {
+        let body = batch_element_error_body(MmError::new(DispatcherError::NoSuchMethod), Json::Null);
+        let parsed: Json = json::from_slice(&body).unwrap();
+        assert_eq!(parsed["id"], Json::Null);
+    }
 }
\ No newline at end of file
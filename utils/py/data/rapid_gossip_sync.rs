@@ -0,0 +1,167 @@
+//! Rapid Gossip Sync (RGS) client: fetches a compact routing-graph snapshot over HTTPS instead of
+//! rebuilding the graph from peer-to-peer gossip, which is slow and bandwidth-heavy on mobile/wasm.
+//!
+//! Wire format is a single binary blob: a version byte, a `latest_seen` timestamp, a table of
+//! default channel-update values, then a run of node announcements followed by channel
+//! announcements and channel updates encoded as deltas against that default table (each entry only
+//! carries the short channel id plus the fields that differ from the default). Decoding replays
+//! those deltas into LDK's `NetworkGraph` via `RapidGossipSync::update_network_graph`.
+//!
+//! Snapshots older than the graph's current `latest_seen` are rejected outright, and the p2p
+//! gossip subscription is left running after a bulk import so the graph stays live afterwards.
+
+use coins::lightning::{LightningCoin, LightningLogger};
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use lightning::routing::gossip::NetworkGraph;
+use lightning_rapid_gossip_sync::RapidGossipSync;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const RGS_SNAPSHOT_VERSION: u8 = 1;
+
+pub type SyncNetworkGraphResult = Result<SyncNetworkGraphResponse, MmError<SyncNetworkGraphError>>;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncNetworkGraphRequest {
+    pub coin: String,
+    /// RGS server base URL; `latest_seen` is appended to its path so the server only has to send
+    /// the delta since our last successful sync.
+    pub rgs_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncNetworkGraphResponse {
+    pub latest_seen: u32,
+    pub node_announcements_applied: usize,
+    pub channel_updates_applied: usize,
+}
+
+#[derive(Serialize, Display, Debug)]
+pub enum SyncNetworkGraphError {
+    #[display(fmt = "No such coin '{}' or it's not a lightning coin", _0)]
+    NoSuchCoin(String),
+    #[display(fmt = "Failed to fetch RGS snapshot: {}", _0)]
+    FetchError(String),
+    #[display(fmt = "Malformed RGS snapshot: {}", _0)]
+    MalformedSnapshot(String),
+    #[display(fmt = "Unsupported RGS snapshot version {}, expected {}", _0, _1)]
+    UnsupportedVersion(u8, u8),
+    #[display(
+        fmt = "Snapshot latest_seen {} is not newer than the graph's current latest_seen {}, ignoring it",
+        snapshot_latest_seen,
+        graph_latest_seen
+    )]
+    StaleSnapshot {
+        snapshot_latest_seen: u32,
+        graph_latest_seen: u32,
+    },
+}
+
+impl HttpStatusCode for SyncNetworkGraphError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SyncNetworkGraphError::NoSuchCoin(_) => StatusCode::NOT_FOUND,
+            SyncNetworkGraphError::StaleSnapshot { .. } => StatusCode::OK,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Fetches a Rapid Gossip Sync snapshot for `coin` and applies it to its in-memory `NetworkGraph`.
+/// Passing the coin's last successful `latest_seen` in the request URL makes the server respond
+/// with only the incremental deltas since then; the full blob is only fetched on a fresh node.
+pub async fn sync_network_graph(ctx: MmArc, req: SyncNetworkGraphRequest) -> SyncNetworkGraphResult {
+    let coin = lightning_coin_from_ctx(&ctx, &req.coin)?;
+    let graph = coin.network_graph();
+    let current_latest_seen = coin.rgs_latest_seen();
+
+    let url = format!("{}/{}", req.rgs_url.trim_end_matches('/'), current_latest_seen);
+    let snapshot = fetch_snapshot_bytes(&url)
+        .await
+        .map_to_mm(|e| SyncNetworkGraphError::FetchError(e.to_string()))?;
+
+    let response = apply_snapshot(&graph, &snapshot, current_latest_seen)?;
+    // Record the new baseline so the next sync's request URL only asks the server for the delta
+    // since this snapshot, instead of re-fetching from `current_latest_seen` forever.
+    coin.set_rgs_latest_seen(response.latest_seen);
+    Ok(response)
+}
+
+/// Decodes and replays one RGS snapshot into `graph`, rejecting anything not newer than
+/// `graph_latest_seen` so a slow/duplicate fetch can never roll the graph backwards.
+fn apply_snapshot(graph: &Arc<NetworkGraph<LightningLogger>>, snapshot: &[u8], graph_latest_seen: u32) -> SyncNetworkGraphResult {
+    let version = *snapshot
+        .first()
+        .ok_or_else(|| MmError::new(SyncNetworkGraphError::MalformedSnapshot("empty snapshot".to_owned())))?;
+    if version != RGS_SNAPSHOT_VERSION {
+        return MmError::err(SyncNetworkGraphError::UnsupportedVersion(version, RGS_SNAPSHOT_VERSION));
+    }
+
+    // The snapshot's own declared `latest_seen` must be checked against the graph *before*
+    // `update_network_graph` is called: that call mutates `graph` in place, so validating
+    // staleness only on its return value would let a stale/duplicate snapshot corrupt the graph
+    // (e.g. rolling channel-update fees back to older values) before being rejected.
+    let snapshot_latest_seen = snapshot_declared_latest_seen(snapshot)?;
+    if snapshot_latest_seen <= graph_latest_seen {
+        return MmError::err(SyncNetworkGraphError::StaleSnapshot {
+            snapshot_latest_seen,
+            graph_latest_seen,
+        });
+    }
+
+    let rgs = RapidGossipSync::new(graph.clone(), LightningLogger);
+    let update_result = rgs
+        .update_network_graph(snapshot)
+        .map_to_mm(|e| SyncNetworkGraphError::MalformedSnapshot(format!("{:?}", e)))?;
+
+    Ok(SyncNetworkGraphResponse {
+        latest_seen: snapshot_latest_seen,
+        node_announcements_applied: update_result.node_announcements_applied,
+        channel_updates_applied: update_result.channel_updates_applied,
+    })
+}
+
+/// Reads the `latest_seen` timestamp out of the raw snapshot bytes (the 4 bytes right after the
+/// version byte) without touching the graph, so staleness can be checked before any mutation.
+fn snapshot_declared_latest_seen(snapshot: &[u8]) -> Result<u32, MmError<SyncNetworkGraphError>> {
+    let latest_seen_bytes: [u8; 4] = snapshot
+        .get(1..5)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| MmError::new(SyncNetworkGraphError::MalformedSnapshot("snapshot is shorter than its header".to_owned())))?;
+    Ok(u32::from_be_bytes(latest_seen_bytes))
+}
+
+async fn fetch_snapshot_bytes(url: &str) -> Result<Vec<u8>, reqwest::Error> {
+    Ok(reqwest::get(url).await?.bytes().await?.to_vec())
+}
+
+fn lightning_coin_from_ctx(ctx: &MmArc, ticker: &str) -> Result<LightningCoin, MmError<SyncNetworkGraphError>> {
+    coins::lp_coinfind(ctx, ticker)
+        .ok()
+        .flatten()
+        .and_then(|coin| coin.as_lightning_coin())
+        .or_mm_err(|| SyncNetworkGraphError::NoSuchCoin(ticker.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_latest_seen_reads_header_without_touching_the_rest_of_the_snapshot() {
+        let mut snapshot = vec![RGS_SNAPSHOT_VERSION];
+        snapshot.extend_from_slice(&42u32.to_be_bytes());
+        snapshot.extend_from_slice(&[0xff; 16]); // remaining body, irrelevant to this check
+        assert_eq!(snapshot_declared_latest_seen(&snapshot).unwrap(), 42);
+    }
+
+    #[test]
+    fn declared_latest_seen_rejects_a_truncated_header() {
+        let snapshot = vec![RGS_SNAPSHOT_VERSION, 0, 0];
+        assert!(snapshot_declared_latest_seen(&snapshot).is_err());
+    }
+}
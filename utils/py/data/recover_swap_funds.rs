@@ -0,0 +1,133 @@
+use crate::lp_swap::{mark_swap_finished, recreate_swap_data, RecreateSwapDataError};
+use coins::{CanRefundHtlc, FoundSwapTxSpend, SwapOps};
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub type RecoverSwapFundsResult = Result<RecoverSwapFundsResponse, MmError<RecoverSwapFundsError>>;
+
+#[derive(Debug, Deserialize)]
+pub struct RecoverSwapFundsRequest {
+    pub uuid: Uuid,
+}
+
+/// Terminal (or pending) outcome of a single `recover_swap_funds` call.
+///
+/// Calling the RPC again on an already `Redeemed`/`Refunded` swap is a no-op that reports
+/// `AlreadyFinished` with the same tx hash, so GUIs can poll it blindly after a crash without
+/// double-spending.
+#[derive(Debug, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RecoverSwapFundsResponse {
+    Redeemed { tx_hash: String },
+    Refunded { tx_hash: String },
+    WaitingForUnlock { earliest_retry_at: u64 },
+    AlreadyFinished { tx_hash: Option<String> },
+}
+
+#[derive(Serialize, Display, Debug)]
+pub enum RecoverSwapFundsError {
+    #[display(fmt = "No persisted swap data found for uuid '{}'", _0)]
+    SwapNotFound(Uuid),
+    #[display(fmt = "Failed to query on-chain payment state: {}", _0)]
+    PaymentStatusError(String),
+    #[display(fmt = "Failed to broadcast recovery transaction: {}", _0)]
+    BroadcastError(String),
+}
+
+impl From<RecreateSwapDataError> for RecoverSwapFundsError {
+    fn from(e: RecreateSwapDataError) -> Self { RecoverSwapFundsError::SwapNotFound(e.uuid) }
+}
+
+impl HttpStatusCode for RecoverSwapFundsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RecoverSwapFundsError::SwapNotFound(_) => StatusCode::NOT_FOUND,
+            RecoverSwapFundsError::PaymentStatusError(_) | RecoverSwapFundsError::BroadcastError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+/// Drives a stuck swap to a terminal outcome without the original `swap_loop` process staying alive.
+///
+/// Mirrors the cancel/refund/punish timelock logic of atomic swaps: the locktime is the boundary
+/// between "still redeemable" and "refundable". Built directly on the same [`SwapOps`] primitives
+/// the live `swap_loop` uses to drive a swap, so recovery behaves identically whether the original
+/// process is still running or has been killed and restarted:
+/// 1. Look up whether the counterparty's payment has already been spent on-chain
+///    ([`SwapOps::search_for_swap_tx_spend_other`]); if so, extract the secret from that spend, or
+///    fall back to a secret we already learned and persisted ourselves.
+/// 2. If we hold the secret and our own payment is still unspent, build and broadcast our spend of
+///    the counterparty's payment.
+/// 3. Otherwise check [`SwapOps::can_refund_htlc`] against our own payment's locktime: if it's
+///    past, build and broadcast our refund; if not, report the locktime so the caller can retry.
+///
+/// Idempotent: [`recreate_swap_data`] returns the persisted terminal tx hash once a swap has
+/// settled (by this call or by the original process), so a repeat call just reports it again
+/// instead of re-broadcasting.
+pub async fn recover_swap_funds_rpc(ctx: MmArc, req: RecoverSwapFundsRequest) -> RecoverSwapFundsResult {
+    let swap = recreate_swap_data(&ctx, req.uuid).await?;
+
+    // `recreate_swap_data` persists the terminal tx hash once a swap settles, either by the
+    // original `swap_loop` or by a previous call to this RPC, so a repeat call is a cheap no-op.
+    if let Some(tx_hash) = swap.finished_tx_hash.clone() {
+        return Ok(RecoverSwapFundsResponse::AlreadyFinished { tx_hash: Some(tx_hash) });
+    }
+
+    let other_payment_spend = swap
+        .other_coin
+        .search_for_swap_tx_spend_other(&swap.other_payment_tx, swap.other_payment_search_from_block)
+        .await
+        .map_to_mm(|e| RecoverSwapFundsError::PaymentStatusError(e.to_string()))?;
+
+    let secret = match other_payment_spend {
+        Some(FoundSwapTxSpend::Spent(spend_tx)) => swap
+            .other_coin
+            .extract_secret(&swap.secret_hash, &spend_tx, false)
+            .ok(),
+        _ => swap.known_secret.clone(),
+    };
+
+    if let Some(secret) = secret {
+        let my_payment_unspent = swap
+            .my_coin
+            .search_for_swap_tx_spend_my(&swap.my_payment_tx, swap.my_payment_search_from_block)
+            .await
+            .map_to_mm(|e| RecoverSwapFundsError::PaymentStatusError(e.to_string()))?
+            .is_none();
+
+        if my_payment_unspent {
+            let spend_tx = swap
+                .my_coin
+                .send_taker_spends_maker_payment(&swap.other_payment_tx, &secret, &swap.other_pubkey)
+                .await
+                .map_to_mm(|e| RecoverSwapFundsError::BroadcastError(e.to_string()))?;
+            let tx_hash = spend_tx.tx_hash_as_bytes().to_string();
+            mark_swap_finished(&ctx, swap.uuid, &tx_hash).await;
+            return Ok(RecoverSwapFundsResponse::Redeemed { tx_hash });
+        }
+    }
+
+    match swap.my_coin.can_refund_htlc(swap.my_payment_locktime).await {
+        Ok(CanRefundHtlc::CanRefundNow) => {
+            let refund_tx = swap
+                .my_coin
+                .send_taker_refunds_payment(&swap.my_payment_tx, &swap.other_pubkey, &swap.secret_hash)
+                .await
+                .map_to_mm(|e| RecoverSwapFundsError::BroadcastError(e.to_string()))?;
+            let tx_hash = refund_tx.tx_hash_as_bytes().to_string();
+            mark_swap_finished(&ctx, swap.uuid, &tx_hash).await;
+            Ok(RecoverSwapFundsResponse::Refunded { tx_hash })
+        },
+        Ok(CanRefundHtlc::HaveToWait(earliest_retry_at)) => {
+            Ok(RecoverSwapFundsResponse::WaitingForUnlock { earliest_retry_at })
+        },
+        Err(e) => MmError::err(RecoverSwapFundsError::PaymentStatusError(e.to_string())),
+    }
+}
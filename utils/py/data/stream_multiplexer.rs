@@ -0,0 +1,443 @@
+//! Generic fastest-wins multiplexer for streamers backed by more than one redundant upstream
+//! (e.g. several Electrum servers for the same coin's `tx_history`/`orderbook` streamer).
+//!
+//! One subscriber task per upstream all feed a single `tokio::broadcast` channel. Every update is
+//! tagged with a monotonic key (block height for header streams, txid for tx_history, a
+//! `(pair, sequence)` for orderbook diffs); a bounded set of recently-emitted keys makes the first
+//! arrival win and drops later duplicates from slower sources. A watchdog demotes a source once it
+//! goes quiet past `stall_timeout` (its updates stop being forwarded) and promotes it again at its
+//! first check after the source resumes producing, all without tearing down the client-facing
+//! broadcast channel.
+
+use crate::rpc::lp_commands::streamer_filters::StreamerFilters;
+use common::HttpStatusCode;
+use derive_more::Display;
+use futures::future::AbortHandle;
+use futures::StreamExt;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+
+/// How many recently-emitted keys to remember before evicting the oldest; bounds memory use on
+/// long-lived streamers instead of growing the dedup set forever.
+const RECENT_KEYS_CAPACITY: usize = 4096;
+
+pub trait MultiplexedUpdate: Clone + Send + 'static {
+    type Key: Eq + Hash + Clone + Send;
+
+    /// The monotonic dedup key for this update (block height, txid, `(pair, sequence)`, ...).
+    fn dedup_key(&self) -> Self::Key;
+}
+
+/// One upstream a multiplexed streamer can pull updates from, e.g. a single Electrum connection.
+pub trait UpdateSource: Send + 'static {
+    type Update: MultiplexedUpdate;
+    type Stream: futures::Stream<Item = Self::Update> + Send + Unpin + 'static;
+
+    fn subscribe(&self) -> Self::Stream;
+}
+
+/// Drives N redundant [`UpdateSource`]s into one broadcast channel, deduplicating by
+/// [`MultiplexedUpdate::dedup_key`] and demoting/promoting sources as they stall or recover.
+pub struct RedundantSourceMultiplexer<U: MultiplexedUpdate> {
+    tx: broadcast::Sender<U>,
+    _abort_handles: Vec<AbortHandle>,
+}
+
+impl<U: MultiplexedUpdate> RedundantSourceMultiplexer<U> {
+    /// Spawns one subscriber task per source plus a watchdog, and returns a receiver that carries
+    /// only the first-arrived, deduplicated updates from currently-active sources.
+    pub fn spawn<S>(sources: Vec<S>, stall_timeout: Duration) -> (Self, broadcast::Receiver<U>)
+    where
+        S: UpdateSource<Update = U>,
+    {
+        let (tx, rx) = broadcast::channel(RECENT_KEYS_CAPACITY);
+        let source_count = sources.len();
+        let mut abort_handles = Vec::with_capacity(source_count);
+        let last_seen = Arc::new(Mutex::new(vec![Instant::now(); source_count]));
+        let active: Arc<Vec<AtomicBool>> = Arc::new((0..source_count).map(|_| AtomicBool::new(true)).collect());
+        // Shared by every source task, so a key already emitted by one source is dropped when a
+        // slower source delivers it later.
+        let recent_keys = Arc::new(Mutex::new(RecentKeys::<U::Key>::new(RECENT_KEYS_CAPACITY)));
+
+        for (idx, source) in sources.into_iter().enumerate() {
+            let tx = tx.clone();
+            let last_seen = last_seen.clone();
+            let active = active.clone();
+            let recent_keys = recent_keys.clone();
+            let mut stream = source.subscribe();
+
+            let (fut, abort_handle) = futures::future::abortable(async move {
+                while let Some(update) = stream.next().await {
+                    last_seen.lock().unwrap()[idx] = Instant::now();
+                    // A demoted source keeps running so the watchdog can promote it again at its
+                    // next check after it resumes producing, but its updates aren't forwarded
+                    // while it is demoted.
+                    if !active[idx].load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    if recent_keys.lock().unwrap().insert_if_new(update.dedup_key()) {
+                        let _ = tx.send(update);
+                    }
+                }
+            });
+            common::executor::spawn(async {
+                let _ = fut.await;
+            });
+            abort_handles.push(abort_handle);
+        }
+
+        Self::spawn_watchdog(last_seen, active, stall_timeout);
+
+        (
+            RedundantSourceMultiplexer {
+                tx,
+                _abort_handles: abort_handles,
+            },
+            rx,
+        )
+    }
+
+    /// Every `stall_timeout / 2`, demotes any source that hasn't produced an update in
+    /// `stall_timeout` (its updates stop being forwarded) and promotes any demoted source that has
+    /// started producing again, logging each transition.
+    fn spawn_watchdog(last_seen: Arc<Mutex<Vec<Instant>>>, active: Arc<Vec<AtomicBool>>, stall_timeout: Duration) {
+        common::executor::spawn(async move {
+            let mut interval = tokio::time::interval(stall_timeout / 2);
+            loop {
+                interval.tick().await;
+                let now = Instant::now();
+                let snapshot = last_seen.lock().unwrap().clone();
+                for (idx, last) in snapshot.iter().enumerate() {
+                    let is_stalled = now.duration_since(*last) > stall_timeout;
+                    let was_active = active[idx].swap(!is_stalled, Ordering::Relaxed);
+                    if was_active && is_stalled {
+                        common::log::warn!(
+                            "multiplexed streamer: source {} stalled for over {:?}, demoting it",
+                            idx,
+                            stall_timeout
+                        );
+                    } else if !was_active && !is_stalled {
+                        common::log::info!("multiplexed streamer: source {} is producing again, promoting it", idx);
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<U> { self.tx.subscribe() }
+}
+
+/// Bounded FIFO of dedup keys already emitted, backed by a `HashSet` for O(1) membership tests
+/// instead of scanning the whole window on every single update from every source.
+struct RecentKeys<K> {
+    order: VecDeque<K>,
+    seen: HashSet<K>,
+    capacity: usize,
+}
+
+impl<K: Eq + Hash + Clone> RecentKeys<K> {
+    fn new(capacity: usize) -> Self {
+        RecentKeys {
+            order: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns `true` (and remembers the key) the first time `key` is seen; `false` otherwise.
+    fn insert_if_new(&mut self, key: K) -> bool {
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.seen.remove(&evicted);
+            }
+        }
+        self.order.push_back(key);
+        true
+    }
+}
+
+// --- Concrete wiring reachable from the RPC surface: `stream::tx_history::enable_multiplexed` and
+// `stream::orderbook::enable_multiplexed` in `rpc_streaming_dispatcher`. ---
+
+#[derive(Clone, Debug)]
+pub struct TxHistoryUpdate {
+    pub txid: String,
+    pub confirmations: u32,
+}
+
+impl MultiplexedUpdate for TxHistoryUpdate {
+    type Key = String;
+
+    fn dedup_key(&self) -> Self::Key { self.txid.clone() }
+}
+
+/// One Electrum endpoint backing a multiplexed `tx_history` streamer; `subscribe` opens its own
+/// connection and is independent of every other source feeding the same multiplexer.
+pub struct ElectrumTxHistorySource {
+    pub url: String,
+}
+
+impl UpdateSource for ElectrumTxHistorySource {
+    type Update = TxHistoryUpdate;
+    type Stream = Pin<Box<dyn futures::Stream<Item = TxHistoryUpdate> + Send>>;
+
+    fn subscribe(&self) -> Self::Stream {
+        let url = self.url.clone();
+        Box::pin(futures::stream::unfold(url, |url| async move {
+            let update = coins::utxo::rpc_clients::electrum_subscribe_tx_history(&url)
+                .await
+                .ok()
+                .map(|(txid, confirmations)| TxHistoryUpdate { txid, confirmations });
+            update.map(|update| (update, url))
+        }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnableMultiplexedTxHistoryRequest {
+    pub coin: String,
+    /// At least one redundant Electrum endpoint to race against each other.
+    pub sources: Vec<String>,
+    #[serde(default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u64,
+    /// Optional filter applied before an update reaches the subscriber channel; can be replaced
+    /// later without resubscribing via `tx_history::update_filter` (see `streamer_filters`).
+    #[serde(default)]
+    pub filter: Option<crate::rpc::lp_commands::streamer_filters::TxHistoryFilter>,
+}
+
+fn default_stall_timeout_secs() -> u64 { 30 }
+
+#[derive(Debug, Serialize)]
+pub struct EnableMultiplexedStreamerResponse {
+    pub streamer_id: String,
+}
+
+#[derive(Serialize, Display, Debug)]
+pub enum MultiplexedStreamerError {
+    #[display(fmt = "`sources` must not be empty")]
+    NoSources,
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for MultiplexedStreamerError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            MultiplexedStreamerError::NoSources => StatusCode::BAD_REQUEST,
+            MultiplexedStreamerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Starts a fastest-wins/failover `tx_history` streamer fed by `req.sources`, applying `req.filter`
+/// (if any) on the producer side before an update is published into the subscriber channel.
+pub async fn enable_tx_history_multiplexed(
+    ctx: MmArc,
+    req: EnableMultiplexedTxHistoryRequest,
+) -> Result<EnableMultiplexedStreamerResponse, MmError<MultiplexedStreamerError>> {
+    if req.sources.is_empty() {
+        return MmError::err(MultiplexedStreamerError::NoSources);
+    }
+
+    let streamer_id = format!("{}_multiplexed_tx_history", req.coin);
+    if let Some(filter) = req.filter {
+        let filters = StreamerFilters::from_ctx(&ctx).map_to_mm(MultiplexedStreamerError::Internal)?;
+        filters.set_tx_history_filter(streamer_id.clone(), filter);
+    }
+
+    let sources: Vec<ElectrumTxHistorySource> = req
+        .sources
+        .into_iter()
+        .map(|url| ElectrumTxHistorySource { url })
+        .collect();
+    let (multiplexer, rx) = RedundantSourceMultiplexer::spawn(sources, Duration::from_secs(req.stall_timeout_secs));
+    let filtered_rx = spawn_filtering_relay(ctx.clone(), streamer_id.clone(), rx);
+    ctx.event_stream_manager()
+        .register_multiplexed_tx_history(&streamer_id, multiplexer, filtered_rx);
+
+    Ok(EnableMultiplexedStreamerResponse { streamer_id })
+}
+
+/// Drains `rx`, drops every update that the streamer's current filter (if any) rejects, and
+/// forwards the rest onto a fresh broadcast channel so the filter can be swapped live by
+/// `streamer_id` without tearing down the upstream subscriber tasks.
+fn spawn_filtering_relay(
+    ctx: MmArc,
+    streamer_id: String,
+    mut rx: broadcast::Receiver<TxHistoryUpdate>,
+) -> broadcast::Receiver<TxHistoryUpdate> {
+    let (tx, filtered_rx) = broadcast::channel(RECENT_KEYS_CAPACITY);
+    common::executor::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(update) => {
+                    let filter = StreamerFilters::from_ctx(&ctx)
+                        .map(|filters| filters.tx_history_filter(&streamer_id))
+                        .unwrap_or_default();
+                    if filter.matches(&update.txid, update.confirmations) {
+                        let _ = tx.send(update);
+                    }
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    filtered_rx
+}
+
+#[derive(Clone, Debug)]
+pub struct OrderbookUpdate {
+    pub base: String,
+    pub rel: String,
+    pub sequence: u64,
+    pub best_price: BigDecimal,
+    pub order_price: BigDecimal,
+}
+
+impl MultiplexedUpdate for OrderbookUpdate {
+    type Key = (String, String, u64);
+
+    fn dedup_key(&self) -> Self::Key { (self.base.clone(), self.rel.clone(), self.sequence) }
+}
+
+/// One Electrum endpoint backing a multiplexed `orderbook` streamer; `subscribe` opens its own
+/// connection and is independent of every other source feeding the same multiplexer.
+pub struct ElectrumOrderbookSource {
+    pub url: String,
+}
+
+impl UpdateSource for ElectrumOrderbookSource {
+    type Update = OrderbookUpdate;
+    type Stream = Pin<Box<dyn futures::Stream<Item = OrderbookUpdate> + Send>>;
+
+    fn subscribe(&self) -> Self::Stream {
+        let url = self.url.clone();
+        Box::pin(futures::stream::unfold(url, |url| async move {
+            let update = coins::utxo::rpc_clients::electrum_subscribe_orderbook(&url)
+                .await
+                .ok()
+                .map(|(base, rel, sequence, best_price, order_price)| OrderbookUpdate {
+                    base,
+                    rel,
+                    sequence,
+                    best_price,
+                    order_price,
+                });
+            update.map(|update| (update, url))
+        }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnableMultiplexedOrderbookRequest {
+    pub coin: String,
+    /// At least one redundant Electrum endpoint to race against each other.
+    pub sources: Vec<String>,
+    #[serde(default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u64,
+    /// Optional filter applied before an update reaches the subscriber channel; can be replaced
+    /// later without resubscribing via `orderbook::update_filter` (see `streamer_filters`).
+    #[serde(default)]
+    pub filter: Option<crate::rpc::lp_commands::streamer_filters::OrderbookFilter>,
+}
+
+/// Starts a fastest-wins/failover `orderbook` streamer fed by `req.sources`, applying `req.filter`
+/// (if any) on the producer side before an update is published into the subscriber channel.
+pub async fn enable_orderbook_multiplexed(
+    ctx: MmArc,
+    req: EnableMultiplexedOrderbookRequest,
+) -> Result<EnableMultiplexedStreamerResponse, MmError<MultiplexedStreamerError>> {
+    if req.sources.is_empty() {
+        return MmError::err(MultiplexedStreamerError::NoSources);
+    }
+
+    let streamer_id = format!("{}_multiplexed_orderbook", req.coin);
+    if let Some(filter) = req.filter {
+        let filters = StreamerFilters::from_ctx(&ctx).map_to_mm(MultiplexedStreamerError::Internal)?;
+        filters.set_orderbook_filter(streamer_id.clone(), filter);
+    }
+
+    let sources: Vec<ElectrumOrderbookSource> = req
+        .sources
+        .into_iter()
+        .map(|url| ElectrumOrderbookSource { url })
+        .collect();
+    let (multiplexer, rx) = RedundantSourceMultiplexer::spawn(sources, Duration::from_secs(req.stall_timeout_secs));
+    let filtered_rx = spawn_filtering_orderbook_relay(ctx.clone(), streamer_id.clone(), rx);
+    ctx.event_stream_manager()
+        .register_multiplexed_orderbook(&streamer_id, multiplexer, filtered_rx);
+
+    Ok(EnableMultiplexedStreamerResponse { streamer_id })
+}
+
+/// Drains `rx`, drops every update that the streamer's current filter (if any) rejects, and
+/// forwards the rest onto a fresh broadcast channel, mirroring `spawn_filtering_relay` for the
+/// `tx_history` side.
+fn spawn_filtering_orderbook_relay(
+    ctx: MmArc,
+    streamer_id: String,
+    mut rx: broadcast::Receiver<OrderbookUpdate>,
+) -> broadcast::Receiver<OrderbookUpdate> {
+    let (tx, filtered_rx) = broadcast::channel(RECENT_KEYS_CAPACITY);
+    common::executor::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(update) => {
+                    let filter = StreamerFilters::from_ctx(&ctx)
+                        .map(|filters| filters.orderbook_filter(&streamer_id))
+                        .unwrap_or_default();
+                    if filter.matches_pair(&update.base, &update.rel)
+                        && filter.matches_price(&update.best_price, &update.order_price)
+                    {
+                        let _ = tx.send(update);
+                    }
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    filtered_rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_if_new_drops_duplicates() {
+        let mut keys = RecentKeys::<u32>::new(4);
+        assert!(keys.insert_if_new(1));
+        assert!(keys.insert_if_new(2));
+        assert!(!keys.insert_if_new(1), "a key already seen must not be inserted again");
+    }
+
+    #[test]
+    fn insert_if_new_evicts_oldest_once_capacity_is_reached() {
+        let mut keys = RecentKeys::<u32>::new(2);
+        assert!(keys.insert_if_new(1));
+        assert!(keys.insert_if_new(2));
+        assert!(keys.insert_if_new(3), "inserting past capacity must evict the oldest key");
+        // Key `1` was evicted to make room for `3`, so it's treated as new again.
+        assert!(keys.insert_if_new(1));
+        // Re-inserting `1` in turn evicted `2`; key `3` is still within the window and must still
+        // be deduplicated.
+        assert!(!keys.insert_if_new(3));
+    }
+}
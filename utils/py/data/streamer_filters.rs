@@ -0,0 +1,181 @@
+//! Client-supplied filters for the `tx_history` and `orderbook` streamers, evaluated on the
+//! producer side before an update is ever published into a subscriber's channel. This mirrors the
+//! account/transaction filter model from streaming RPC frontends and keeps mobile clients from
+//! paying to deserialize diffs they'd only discard.
+//!
+//! A filter can be supplied up front in the `enable` call (`tx_history::enable_multiplexed` and
+//! `orderbook::enable_multiplexed`'s `filter` fields are the two enable-paths this series actually
+//! owns end to end; see the module doc in `stream_multiplexer`) or replaced on a live subscription
+//! with `<streamer>::update_filter` without resubscribing, since filters are looked up by
+//! `streamer_id` in [`StreamerFilters`] rather than being baked into the producer task's captured
+//! state.
+//!
+//! Scope note: the plain, non-multiplexed `tx_history::enable`/`orderbook::enable` streamers live in
+//! `streaming_activations`, which this series doesn't touch, so a filter set against one of those
+//! `streamer_id`s is stored but never consulted by a producer. Only the multiplexed paths above
+//! evaluate filters today.
+
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Filter for the `tx_history` streamer: only addresses/scripts in `watch_addresses` are forwarded,
+/// and an update with fewer than `min_confirmations` confirmations is dropped.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TxHistoryFilter {
+    /// Empty means "no address filter", i.e. the full firehose for the enabled coin.
+    #[serde(default)]
+    pub watch_addresses: Vec<String>,
+    #[serde(default)]
+    pub min_confirmations: u32,
+}
+
+impl TxHistoryFilter {
+    pub fn matches(&self, tx_address: &str, confirmations: u32) -> bool {
+        confirmations >= self.min_confirmations
+            && (self.watch_addresses.is_empty() || self.watch_addresses.iter().any(|a| a == tx_address))
+    }
+}
+
+/// Filter for the `orderbook` streamer: only diffs for `pairs` are forwarded, and `price_band`
+/// (when set) drops orders outside `[best_price * (1 - band), best_price * (1 + band)]`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct OrderbookFilter {
+    /// Empty means "all pairs this node tracks".
+    #[serde(default)]
+    pub pairs: Vec<(String, String)>,
+    pub depth: Option<usize>,
+    pub price_band: Option<BigDecimal>,
+}
+
+impl OrderbookFilter {
+    pub fn matches_pair(&self, base: &str, rel: &str) -> bool {
+        self.pairs.is_empty()
+            || self
+                .pairs
+                .iter()
+                .any(|(f_base, f_rel)| f_base == base && f_rel == rel)
+    }
+
+    pub fn matches_price(&self, best_price: &BigDecimal, order_price: &BigDecimal) -> bool {
+        match &self.price_band {
+            None => true,
+            Some(band) => {
+                let lower = best_price * (BigDecimal::from(1) - band);
+                let upper = best_price * (BigDecimal::from(1) + band);
+                *order_price >= lower && *order_price <= upper
+            },
+        }
+    }
+}
+
+/// Per-`streamer_id` filter storage, looked up by the producer task on every update and updated
+/// in place by `<streamer>::update_filter`, so swapping a filter never requires tearing down and
+/// resubscribing the underlying streamer.
+#[derive(Default)]
+pub struct StreamerFilters {
+    tx_history: Mutex<HashMap<String, TxHistoryFilter>>,
+    orderbook: Mutex<HashMap<String, OrderbookFilter>>,
+}
+
+impl StreamerFilters {
+    pub fn from_ctx(ctx: &MmArc) -> Result<Arc<StreamerFilters>, String> {
+        from_ctx(&ctx.streamer_filters, || Ok(StreamerFilters::default()))
+    }
+
+    pub fn set_tx_history_filter(&self, streamer_id: String, filter: TxHistoryFilter) {
+        self.tx_history.lock().unwrap().insert(streamer_id, filter);
+    }
+
+    /// The streamer's current filter, or the default (no-op) filter if none was ever set.
+    pub fn tx_history_filter(&self, streamer_id: &str) -> TxHistoryFilter {
+        self.tx_history.lock().unwrap().get(streamer_id).cloned().unwrap_or_default()
+    }
+
+    pub fn set_orderbook_filter(&self, streamer_id: String, filter: OrderbookFilter) {
+        self.orderbook.lock().unwrap().insert(streamer_id, filter);
+    }
+
+    pub fn orderbook_filter(&self, streamer_id: &str) -> OrderbookFilter {
+        self.orderbook.lock().unwrap().get(streamer_id).cloned().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTxHistoryFilterRequest {
+    pub streamer_id: String,
+    pub filter: TxHistoryFilter,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOrderbookFilterRequest {
+    pub streamer_id: String,
+    pub filter: OrderbookFilter,
+}
+
+#[derive(Serialize, derive_more::Display, Debug)]
+pub enum StreamerFilterError {
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl common::HttpStatusCode for StreamerFilterError {
+    fn status_code(&self) -> http::StatusCode { http::StatusCode::INTERNAL_SERVER_ERROR }
+}
+
+pub async fn update_tx_history_filter(ctx: MmArc, req: UpdateTxHistoryFilterRequest) -> Result<(), MmError<StreamerFilterError>> {
+    let filters = StreamerFilters::from_ctx(&ctx).map_to_mm(StreamerFilterError::Internal)?;
+    filters.set_tx_history_filter(req.streamer_id, req.filter);
+    Ok(())
+}
+
+pub async fn update_orderbook_filter(ctx: MmArc, req: UpdateOrderbookFilterRequest) -> Result<(), MmError<StreamerFilterError>> {
+    let filters = StreamerFilters::from_ctx(&ctx).map_to_mm(StreamerFilterError::Internal)?;
+    filters.set_orderbook_filter(req.streamer_id, req.filter);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_history_filter_requires_min_confirmations() {
+        let filter = TxHistoryFilter {
+            watch_addresses: vec![],
+            min_confirmations: 3,
+        };
+        assert!(!filter.matches("any_address", 2));
+        assert!(filter.matches("any_address", 3));
+    }
+
+    #[test]
+    fn tx_history_filter_with_watch_addresses_drops_unwatched() {
+        let filter = TxHistoryFilter {
+            watch_addresses: vec!["addr1".to_owned()],
+            min_confirmations: 0,
+        };
+        assert!(filter.matches("addr1", 0));
+        assert!(!filter.matches("addr2", 0));
+    }
+
+    #[test]
+    fn orderbook_filter_empty_pairs_matches_everything() {
+        let filter = OrderbookFilter::default();
+        assert!(filter.matches_pair("RICK", "MORTY"));
+    }
+
+    #[test]
+    fn orderbook_filter_restricts_to_configured_pairs() {
+        let filter = OrderbookFilter {
+            pairs: vec![("RICK".to_owned(), "MORTY".to_owned())],
+            depth: None,
+            price_band: None,
+        };
+        assert!(filter.matches_pair("RICK", "MORTY"));
+        assert!(!filter.matches_pair("KMD", "BTC"));
+    }
+}
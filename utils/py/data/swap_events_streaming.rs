@@ -0,0 +1,132 @@
+//! Pushes incremental swap state-machine transitions (negotiated, payment sent, payment spent,
+//! refunded) onto the ctx event bus instead of requiring a client to poll `my_swap_status` for
+//! every swap in flight.
+//!
+//! `streaming_activations::enable_swap_status` already pushes the *polled* status snapshot on an
+//! interval; this is the event-driven counterpart `streaming_activations` doesn't otherwise own, so
+//! it lives here as its own module. [`SwapEventBus`] is the ctx-scoped broadcast channel: `swap_loop`
+//! calls [`emit_swap_event`] on every transition, and [`enable_swap_events`] hands a subscriber
+//! a filtered (by `uuid`, if requested) receiver registered with the event stream manager the same
+//! way every other streamer in this series is, via `stream::disable`; there is no per-streamer
+//! `stream::<id>::disable` method.
+
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// How many in-flight deltas the bus buffers per subscriber before a slow client starts lagging
+/// (and silently skipping ahead, same as every other broadcast-backed streamer in this series).
+const SWAP_EVENT_BUS_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SwapEventKind {
+    Negotiated,
+    PaymentSent { tx_hash: String },
+    PaymentSpent { tx_hash: String },
+    Refunded { tx_hash: String },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SwapEventUpdate {
+    pub uuid: Uuid,
+    #[serde(flatten)]
+    pub kind: SwapEventKind,
+}
+
+/// Ctx-scoped broadcast channel every swap transition is published onto; `swap_loop` is the
+/// intended caller of [`emit_swap_event`], one per state-machine transition.
+pub struct SwapEventBus {
+    tx: broadcast::Sender<SwapEventUpdate>,
+}
+
+impl Default for SwapEventBus {
+    fn default() -> Self {
+        let (tx, _rx) = broadcast::channel(SWAP_EVENT_BUS_CAPACITY);
+        SwapEventBus { tx }
+    }
+}
+
+impl SwapEventBus {
+    pub fn from_ctx(ctx: &MmArc) -> Result<Arc<SwapEventBus>, String> { from_ctx(&ctx.swap_event_bus, || Ok(SwapEventBus::default())) }
+
+    fn subscribe(&self) -> broadcast::Receiver<SwapEventUpdate> { self.tx.subscribe() }
+}
+
+/// Publishes `update` onto `ctx`'s swap event bus; a no-op if nobody is currently subscribed.
+pub fn emit_swap_event(ctx: &MmArc, update: SwapEventUpdate) {
+    if let Ok(bus) = SwapEventBus::from_ctx(ctx) {
+        let _ = bus.tx.send(update);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnableSwapEventsRequest {
+    /// Restricts the stream to a single swap; omit to receive every swap's transitions.
+    pub uuid: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnableSwapEventsResponse {
+    pub streamer_id: String,
+}
+
+#[derive(Serialize, Display, Debug)]
+pub enum SwapEventsError {
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for SwapEventsError {
+    fn status_code(&self) -> StatusCode { StatusCode::INTERNAL_SERVER_ERROR }
+}
+
+/// Registers a channel on the ctx event bus that carries every swap transition matching `req.uuid`
+/// (or all of them), disabled the same way as every other streamer, via `stream::disable`.
+pub async fn enable_swap_events(
+    ctx: MmArc,
+    req: EnableSwapEventsRequest,
+) -> Result<EnableSwapEventsResponse, MmError<SwapEventsError>> {
+    let bus = SwapEventBus::from_ctx(&ctx).map_to_mm(SwapEventsError::Internal)?;
+    let streamer_id = match req.uuid {
+        Some(uuid) => format!("swap_events_{}", uuid),
+        None => "swap_events".to_owned(),
+    };
+
+    let rx = bus.subscribe();
+    let filtered_rx = spawn_filtering_relay(streamer_id.clone(), req.uuid, rx);
+    ctx.event_stream_manager().register_swap_events(&streamer_id, filtered_rx);
+
+    Ok(EnableSwapEventsResponse { streamer_id })
+}
+
+/// Drains `rx`, drops every update whose `uuid` doesn't match `only_uuid` (when set), and forwards
+/// the rest onto a fresh broadcast channel dedicated to this subscriber.
+fn spawn_filtering_relay(
+    streamer_id: String,
+    only_uuid: Option<Uuid>,
+    mut rx: broadcast::Receiver<SwapEventUpdate>,
+) -> broadcast::Receiver<SwapEventUpdate> {
+    let (tx, filtered_rx) = broadcast::channel(SWAP_EVENT_BUS_CAPACITY);
+    common::executor::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(update) => {
+                    if only_uuid.map_or(true, |uuid| uuid == update.uuid) {
+                        let _ = tx.send(update);
+                    }
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    common::log::debug!("swap_events: streamer {} ready", streamer_id);
+    filtered_rx
+}
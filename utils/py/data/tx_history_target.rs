@@ -0,0 +1,123 @@
+use crate::hd_wallet::HDAddressId;
+use coins::my_tx_history_v2::{MyTxHistoryRequestV2, MyTxHistoryResponseV2, MyTxHistoryV2Error};
+use coins::{lp_coinfind_or_err, MmCoinEnum};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Selects which address(es) `my_tx_history` should scan and merge transactions from.
+///
+/// Defaults to `Iguana` so existing callers that don't send `target` keep seeing the single
+/// legacy-activated address they always have. HD/Trezor-activated coins (enabled through the
+/// `task::enable_*` init flows) hold funds across more than one derivation path, so a single
+/// fixed address silently hides balance history on anything but the default account/chain/index.
+#[derive(Debug, Default, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum MyTxHistoryTarget {
+    #[default]
+    Iguana,
+    /// A single HD account/chain/address_index, resolved via the coin's HD derivation method.
+    HDAddress(HDAddressId),
+    /// An explicit list of addresses to scan and merge, for GUIs that already track which
+    /// derivation paths hold funds and want to avoid a full HD scan on every history refresh.
+    AddressList(Vec<String>),
+}
+
+/// Resolves `target` into the concrete set of addresses whose transactions should be merged into
+/// one paginated, tagged result (`TxHistoryItem::address`), using the coin's own HD derivation
+/// method so this stays correct for every coin family `my_tx_history` supports.
+pub trait ResolveHistoryTarget {
+    type Error;
+
+    fn resolve_history_addresses(&self, target: &MyTxHistoryTarget) -> Result<Vec<String>, Self::Error>;
+}
+
+impl ResolveHistoryTarget for MmCoinEnum {
+    type Error = MyTxHistoryV2Error;
+
+    fn resolve_history_addresses(&self, target: &MyTxHistoryTarget) -> Result<Vec<String>, Self::Error> {
+        match target {
+            MyTxHistoryTarget::Iguana => Ok(vec![self.my_address()?]),
+            MyTxHistoryTarget::HDAddress(address_id) => self.derive_hd_address(address_id).map(|address| vec![address]),
+            MyTxHistoryTarget::AddressList(addresses) => Ok(addresses.clone()),
+        }
+    }
+}
+
+/// `my_tx_history` request, extended with `target` so HD/Trezor-activated coins aren't limited to
+/// the single default-derivation address the framework historically assumed.
+#[derive(Debug, Deserialize)]
+pub struct MyTxHistoryTargetedRequest {
+    #[serde(flatten)]
+    pub base: MyTxHistoryRequestV2,
+    #[serde(default)]
+    pub target: MyTxHistoryTarget,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MyTxHistoryTargetedResponse {
+    #[serde(flatten)]
+    pub base: MyTxHistoryResponseV2,
+}
+
+/// Resolves `target` into a concrete address set, fetches each address's history through the
+/// existing per-address RPC, then merges and re-sorts the results, tagging each entry with the
+/// address it belongs to so a GUI can tell which derivation path a transaction came from.
+pub async fn my_tx_history_rpc(
+    ctx: MmArc,
+    req: MyTxHistoryTargetedRequest,
+) -> Result<MyTxHistoryTargetedResponse, MmError<MyTxHistoryV2Error>> {
+    let coin = lp_coinfind_or_err(&ctx, &req.base.coin).await?;
+    let addresses = coin.resolve_history_addresses(&req.target)?;
+    let limit = req.base.limit;
+
+    let mut transactions = Vec::new();
+    let mut total = 0usize;
+    for address in &addresses {
+        let per_address_req = MyTxHistoryRequestV2 {
+            address: Some(address.clone()),
+            ..req.base.clone()
+        };
+        let response = coins::my_tx_history_v2::my_tx_history_v2_rpc(ctx.clone(), per_address_req).await?;
+        total += response.total;
+        transactions.extend(response.transactions.into_iter().map(|mut tx| {
+            tx.address = address.clone();
+            tx
+        }));
+    }
+    transactions.sort_unstable_by(|a, b| b.block_height.cmp(&a.block_height));
+    // Each per-address call already asked for up to `limit` of its own history, so the merge above
+    // can hand back up to `addresses.len() * limit` entries; clamp to what the caller actually asked
+    // for so the merged result is a single page, not a multiple of one.
+    transactions.truncate(limit);
+
+    Ok(MyTxHistoryTargetedResponse {
+        base: MyTxHistoryResponseV2 {
+            transactions,
+            total,
+            limit,
+            ..Default::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_target_is_iguana() {
+        // `Iguana` is the default so a request with no `target` at all still behaves exactly like
+        // the pre-existing single-address history lookup.
+        assert!(matches!(MyTxHistoryTarget::default(), MyTxHistoryTarget::Iguana));
+    }
+
+    #[test]
+    fn address_list_target_is_used_verbatim() {
+        let target = MyTxHistoryTarget::AddressList(vec!["addr1".to_owned(), "addr2".to_owned()]);
+        match target {
+            MyTxHistoryTarget::AddressList(addresses) => assert_eq!(addresses, vec!["addr1", "addr2"]),
+            _ => panic!("expected AddressList"),
+        }
+    }
+}
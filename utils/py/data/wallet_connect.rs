@@ -0,0 +1,238 @@
+use coins::{lp_coinfind_or_err, RawTransactionError, RawTransactionRequest, RawTransactionRes,
+           SignatureError, SignatureRequest, SignatureResponse, WalletConnectConnectionType, WithdrawError,
+           WithdrawRequest};
+use coins::TransactionDetails;
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde::{Deserialize, Serialize};
+use wc_session::{PairingTopic, SessionTopic, WalletConnectCtx};
+
+/// One outstanding request sent to the external wallet over an active session: either a raw
+/// payload to sign (`personal_sign`-style) or a full transaction to sign and return (`sign_tx`).
+#[derive(Debug)]
+pub enum SessionRequest {
+    SignPayload { payload: Vec<u8> },
+    SignTransaction { unsigned_tx: Vec<u8> },
+}
+
+pub type WcRpcResult<T> = Result<T, MmError<WcRpcError>>;
+
+#[derive(Debug, Deserialize)]
+pub struct NewConnectionRequest {
+    /// Optional set of chain ids the dapp intends to use; defaults to the coins already enabled in `coins.json`.
+    pub required_namespaces: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewConnectionResponse {
+    /// `wc:` pairing URI to be rendered as a QR code or deep-linked to the signer wallet.
+    pub pairing_uri: String,
+    pub topic: PairingTopic,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetSessionsRequest {}
+
+#[derive(Debug, Serialize)]
+pub struct GetSessionsResponse {
+    pub sessions: Vec<SessionInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub topic: SessionTopic,
+    pub peer_name: String,
+    pub connected_accounts: Vec<String>,
+    pub expiry: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PingSessionRequest {
+    pub topic: SessionTopic,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteConnectionRequest {
+    pub topic: SessionTopic,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetAccountRequest {
+    pub topic: SessionTopic,
+    pub chain_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAccountResponse {
+    pub account: String,
+}
+
+#[derive(Serialize, Display, Debug)]
+pub enum WcRpcError {
+    #[display(fmt = "WalletConnect context is not initialized, enable it first")]
+    CtxNotInitialized,
+    #[display(fmt = "No such session with topic '{}'", _0)]
+    SessionNotFound(SessionTopic),
+    #[display(fmt = "WalletConnect internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for WcRpcError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WcRpcError::CtxNotInitialized => StatusCode::INTERNAL_SERVER_ERROR,
+            WcRpcError::SessionNotFound(_) => StatusCode::NOT_FOUND,
+            WcRpcError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Returns the `WalletConnectCtx` stored on `MmArc`, failing if `wc::new_connection` was never called.
+fn wc_ctx_from_mm_arc(ctx: &MmArc) -> WcRpcResult<WalletConnectCtx> {
+    WalletConnectCtx::from_ctx(ctx).mm_err(|_| WcRpcError::CtxNotInitialized)
+}
+
+/// Starts a new pairing and returns the `wc:...` URI for the dapp/signer to scan or deep-link.
+pub async fn new_connection(ctx: MmArc, req: NewConnectionRequest) -> WcRpcResult<NewConnectionResponse> {
+    let wc_ctx = WalletConnectCtx::init_or_get(&ctx).await.mm_err(|e| WcRpcError::Internal(e.to_string()))?;
+    let (pairing_uri, topic) = wc_ctx
+        .create_pairing(req.required_namespaces)
+        .await
+        .mm_err(|e| WcRpcError::Internal(e.to_string()))?;
+    Ok(NewConnectionResponse { pairing_uri, topic })
+}
+
+pub async fn get_sessions(ctx: MmArc, _req: GetSessionsRequest) -> WcRpcResult<GetSessionsResponse> {
+    let wc_ctx = wc_ctx_from_mm_arc(&ctx)?;
+    let sessions = wc_ctx
+        .active_sessions()
+        .await
+        .into_iter()
+        .map(|session| SessionInfo {
+            topic: session.topic,
+            peer_name: session.peer_metadata.name,
+            connected_accounts: session.accounts,
+            expiry: session.expiry,
+        })
+        .collect();
+    Ok(GetSessionsResponse { sessions })
+}
+
+/// Sends a `wc_sessionPing` request over the session and waits for the pong so GUIs can show a live/stale indicator.
+pub async fn ping_session(ctx: MmArc, req: PingSessionRequest) -> WcRpcResult<()> {
+    let wc_ctx = wc_ctx_from_mm_arc(&ctx)?;
+    wc_ctx
+        .ping_session(&req.topic)
+        .await
+        .mm_err(|_| WcRpcError::SessionNotFound(req.topic))
+}
+
+pub async fn delete_connection(ctx: MmArc, req: DeleteConnectionRequest) -> WcRpcResult<()> {
+    let wc_ctx = wc_ctx_from_mm_arc(&ctx)?;
+    wc_ctx
+        .disconnect_session(&req.topic)
+        .await
+        .mm_err(|_| WcRpcError::SessionNotFound(req.topic))
+}
+
+/// Resolves the connected account address for `chain_id`, used by coins configured with
+/// [`WalletConnectConnectionType::WalletConnect`] to populate their address without a local keypair.
+pub async fn get_account(ctx: MmArc, req: GetAccountRequest) -> WcRpcResult<GetAccountResponse> {
+    let wc_ctx = wc_ctx_from_mm_arc(&ctx)?;
+    let account = wc_ctx
+        .account_for_chain(&req.topic, &req.chain_id)
+        .await
+        .mm_err(|_| WcRpcError::SessionNotFound(req.topic))?;
+    Ok(GetAccountResponse { account })
+}
+
+/// Sends `request` over the session tied to the coin's [`WalletConnectConnectionType::WalletConnect`]
+/// account and blocks until the external wallet returns a signature, or the request times out.
+pub async fn request_remote_signature(
+    ctx: &MmArc,
+    connection: &WalletConnectConnectionType,
+    request: SessionRequest,
+) -> WcRpcResult<Vec<u8>> {
+    let wc_ctx = wc_ctx_from_mm_arc(ctx)?;
+    wc_ctx
+        .send_session_request(connection.topic(), request)
+        .await
+        .mm_err(|e| WcRpcError::Internal(e.to_string()))
+}
+
+/// Coin-level connection type for `ticker`, or `None` if the coin isn't enabled at all (the
+/// underlying handler is left to report that error itself).
+async fn wallet_connect_connection_for(ctx: &MmArc, ticker: &str) -> Option<WalletConnectConnectionType> {
+    let coin = lp_coinfind_or_err(ctx, ticker).await.ok()?;
+    coin.wallet_connect_connection()
+}
+
+/// `sign_message` dispatcher wrapper: forwards to the connected external wallet when `req.coin` is
+/// configured with [`WalletConnectConnectionType::WalletConnect`], otherwise signs locally exactly
+/// as before.
+pub async fn sign_message_wc_aware(
+    ctx: MmArc,
+    req: SignatureRequest,
+) -> Result<SignatureResponse, MmError<SignatureError>> {
+    if let Some(connection) = wallet_connect_connection_for(&ctx, &req.coin).await {
+        let signature = request_remote_signature(&ctx, &connection, SessionRequest::SignPayload {
+            payload: req.message.clone().into_bytes(),
+        })
+        .await
+        .map_to_mm(|e| SignatureError::InternalError(e.to_string()))?;
+        return Ok(SignatureResponse {
+            signature: hex::encode(signature),
+        });
+    }
+
+    coins::sign_message(ctx, req).await
+}
+
+/// `withdraw` dispatcher wrapper: builds the unsigned transaction exactly as the local-keypair
+/// path does, but when `req.coin` is WalletConnect-backed, sends that unsigned transaction over
+/// the session and waits for the external wallet's signature instead of signing with a local key.
+pub async fn withdraw_wc_aware(ctx: MmArc, req: WithdrawRequest) -> Result<TransactionDetails, MmError<WithdrawError>> {
+    if let Some(connection) = wallet_connect_connection_for(&ctx, &req.coin).await {
+        let coin = lp_coinfind_or_err(&ctx, &req.coin)
+            .await
+            .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+        let unsigned = coin
+            .build_unsigned_withdraw_tx(&req)
+            .await
+            .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+        let signed_tx = request_remote_signature(&ctx, &connection, SessionRequest::SignTransaction {
+            unsigned_tx: unsigned.tx_hex(),
+        })
+        .await
+        .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+        return coin
+            .finalize_and_broadcast_withdraw_tx(unsigned, signed_tx)
+            .await
+            .map_to_mm(|e| WithdrawError::InternalError(e.to_string()));
+    }
+
+    coins::withdraw(ctx, req).await
+}
+
+/// `sign_raw_transaction` dispatcher wrapper: same WalletConnect branch as `withdraw_wc_aware`,
+/// for callers that already built their own unsigned transaction and just need it signed.
+pub async fn sign_raw_transaction_wc_aware(
+    ctx: MmArc,
+    req: RawTransactionRequest,
+) -> Result<RawTransactionRes, MmError<RawTransactionError>> {
+    if let Some(connection) = wallet_connect_connection_for(&ctx, &req.coin).await {
+        let signed_tx = request_remote_signature(&ctx, &connection, SessionRequest::SignTransaction {
+            unsigned_tx: req.tx_hex.clone().into(),
+        })
+        .await
+        .map_to_mm(|e| RawTransactionError::InternalError(e.to_string()))?;
+        return Ok(RawTransactionRes {
+            tx_hex: signed_tx.into(),
+        });
+    }
+
+    coins::sign_raw_transaction(ctx, req).await
+}